@@ -0,0 +1,376 @@
+//! Shared wire encoding for the Ostium trader performance proof's public values.
+//!
+//! This is the single source of truth for the committed byte layout, used by both
+//! the `no_main` guest (to encode) and the host (to decode) so the two sides can
+//! never drift the way hand-written doc-comment offsets and slice indices did.
+//!
+//! On-wire format: `[version: u8][body: N bytes]`, all integers big-endian
+//! (for Solidity compatibility). `decode` dispatches on the version byte, so
+//! a layout change adds a new `decode_vN` arm and a bumped `SCHEMA_VERSION`
+//! instead of breaking existing on-chain verifiers. Each version's body size
+//! is kept under a `_V1`, `_V2`, ... name so its `decode_vN` can still make
+//! sense of proofs already committed on-chain. Field offsets carry no version
+//! suffix: a later version keeps the earlier body unchanged and appends to it.
+
+use std::convert::{TryFrom, TryInto};
+
+pub mod markets;
+pub mod trade_record;
+
+pub use markets::{side_as_bool, PairIndex, Side};
+
+/// Current schema version written by `encode`.
+pub const SCHEMA_VERSION: u8 = 2;
+
+/// Size of the leading version tag.
+pub const VERSION_SIZE: usize = 1;
+
+// --- Version 1 body layout (frozen; also the leading part of the v2 body that `encode` writes) ---
+pub const TRADER_OFFSET: usize = 0;
+pub const TRADE_COUNT_OFFSET: usize = 20;
+pub const WIN_COUNT_OFFSET: usize = 24;
+pub const TOTAL_PNL_OFFSET: usize = 28;
+pub const TOTAL_COLLATERAL_OFFSET: usize = 36;
+pub const START_TIMESTAMP_OFFSET: usize = 44;
+pub const END_TIMESTAMP_OFFSET: usize = 52;
+pub const FEATURED_TRADE_ID_OFFSET: usize = 60;
+pub const FEATURED_PAIR_INDEX_OFFSET: usize = 68;
+pub const FEATURED_IS_BUY_OFFSET: usize = 72;
+pub const FEATURED_LEVERAGE_OFFSET: usize = 73;
+pub const FEATURED_COLLATERAL_OFFSET: usize = 77;
+pub const FEATURED_ENTRY_PRICE_OFFSET: usize = 85;
+pub const FEATURED_IS_OPEN_OFFSET: usize = 101;
+pub const FEATURED_TIMESTAMP_OFFSET: usize = 102;
+
+/// Size of the v1 body, not counting the version tag.
+pub const SERIALIZED_SIZE_V1: usize = 110;
+
+/// Total on-wire size of a v1 commitment (version tag + body).
+pub const TOTAL_SIZE_V1: usize = VERSION_SIZE + SERIALIZED_SIZE_V1;
+
+// --- Version 2 body layout: the v1 body unchanged, plus risk metrics appended after it ---
+pub const GROSS_PNL_OFFSET: usize = 110;
+pub const TOTAL_COST_OFFSET: usize = 118;
+pub const MAX_DRAWDOWN_OFFSET: usize = 126;
+pub const SUM_SQ_RETURNS_OFFSET: usize = 134;
+
+/// Size of the v2 body, not counting the version tag.
+pub const SERIALIZED_SIZE_V2: usize = 150;
+
+/// Total on-wire size of a v2 commitment (version tag + body).
+pub const TOTAL_SIZE_V2: usize = VERSION_SIZE + SERIALIZED_SIZE_V2;
+
+/// Total on-wire size written by `encode` (always the current schema version).
+pub const TOTAL_SIZE: usize = TOTAL_SIZE_V2;
+
+/// Aggregate performance metrics, in the guest's native integer units.
+///
+/// `gross_pnl_micros`, `total_cost_micros`, `max_drawdown_micros` and
+/// `sum_sq_returns_micros` were added in schema v2 — decoding a v1 proof
+/// sets them to `0`, since that version's body genuinely doesn't carry them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metrics {
+    pub trader: [u8; 20],
+    pub trade_count: u32,
+    pub win_count: u32,
+    pub total_pnl_micros: i64,
+    pub total_collateral_micros: u64,
+    pub start_timestamp: u64,
+    pub end_timestamp: u64,
+    /// Raw price-based PnL, before subtracting funding/rollover costs.
+    pub gross_pnl_micros: i64,
+    /// Sum of `abs(funding) + abs(rollover)` across all trades.
+    pub total_cost_micros: u64,
+    /// Largest peak-to-trough drop in cumulative net PnL, over the
+    /// timestamp-ordered equity curve. Always `>= 0`.
+    pub max_drawdown_micros: u64,
+    /// Sum of squared per-trade net PnL, so a verifier can derive variance
+    /// (and a Sharpe-like ratio) from `sum_sq` and `total_pnl_micros` /
+    /// `trade_count` without the guest ever touching a float.
+    pub sum_sq_returns_micros: u128,
+}
+
+/// The featured position, in the guest's native integer units. `pair_index`
+/// and `side` are validated types (see the `markets` module) rather than a
+/// raw `u32`/byte, so a decoded `Featured` can't represent a nonexistent
+/// market or an unrecognized direction code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Featured {
+    pub trade_id: u64,
+    pub pair_index: PairIndex,
+    pub side: Side,
+    pub leverage: u32,
+    pub collateral_micros: u64,
+    pub entry_price: u128,
+    pub is_open: u8,
+    pub timestamp: u64,
+}
+
+/// Why `decode` couldn't make sense of a byte slice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The slice doesn't even contain a version tag.
+    Empty,
+    /// The version tag isn't one this build knows how to decode.
+    UnknownVersion(u8),
+    /// The version is known but the slice is shorter than that version's body.
+    TooShort { version: u8, expected: usize, actual: usize },
+    /// The committed `featured_pair_index` doesn't correspond to any market.
+    InvalidPairIndex(u32),
+    /// The committed `featured_is_buy` byte is neither `0` nor `1`.
+    InvalidSide(u8),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Empty => write!(f, "public values are empty"),
+            DecodeError::UnknownVersion(v) => {
+                write!(f, "unknown public values schema version: {v}")
+            }
+            DecodeError::TooShort { version, expected, actual } => write!(
+                f,
+                "public values too short for schema version {version}: expected {expected} bytes, got {actual}"
+            ),
+            DecodeError::InvalidPairIndex(v) => markets::InvalidPairIndex(*v).fmt(f),
+            DecodeError::InvalidSide(v) => markets::InvalidSideCode(*v).fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<markets::InvalidPairIndex> for DecodeError {
+    fn from(e: markets::InvalidPairIndex) -> Self {
+        DecodeError::InvalidPairIndex(e.0)
+    }
+}
+
+impl From<markets::InvalidSideCode> for DecodeError {
+    fn from(e: markets::InvalidSideCode) -> Self {
+        DecodeError::InvalidSide(e.0)
+    }
+}
+
+/// Encode `metrics` and `featured` as the current schema version's on-wire bytes.
+pub fn encode(metrics: &Metrics, featured: &Featured) -> [u8; TOTAL_SIZE] {
+    let mut out = [0u8; TOTAL_SIZE];
+    out[0] = SCHEMA_VERSION;
+    let body = &mut out[VERSION_SIZE..];
+
+    body[TRADER_OFFSET..TRADER_OFFSET + 20].copy_from_slice(&metrics.trader);
+    body[TRADE_COUNT_OFFSET..TRADE_COUNT_OFFSET + 4]
+        .copy_from_slice(&metrics.trade_count.to_be_bytes());
+    body[WIN_COUNT_OFFSET..WIN_COUNT_OFFSET + 4].copy_from_slice(&metrics.win_count.to_be_bytes());
+    body[TOTAL_PNL_OFFSET..TOTAL_PNL_OFFSET + 8]
+        .copy_from_slice(&metrics.total_pnl_micros.to_be_bytes());
+    body[TOTAL_COLLATERAL_OFFSET..TOTAL_COLLATERAL_OFFSET + 8]
+        .copy_from_slice(&metrics.total_collateral_micros.to_be_bytes());
+    body[START_TIMESTAMP_OFFSET..START_TIMESTAMP_OFFSET + 8]
+        .copy_from_slice(&metrics.start_timestamp.to_be_bytes());
+    body[END_TIMESTAMP_OFFSET..END_TIMESTAMP_OFFSET + 8]
+        .copy_from_slice(&metrics.end_timestamp.to_be_bytes());
+
+    body[FEATURED_TRADE_ID_OFFSET..FEATURED_TRADE_ID_OFFSET + 8]
+        .copy_from_slice(&featured.trade_id.to_be_bytes());
+    body[FEATURED_PAIR_INDEX_OFFSET..FEATURED_PAIR_INDEX_OFFSET + 4]
+        .copy_from_slice(&featured.pair_index.get().to_be_bytes());
+    body[FEATURED_IS_BUY_OFFSET] = featured.side.to_u8();
+    body[FEATURED_LEVERAGE_OFFSET..FEATURED_LEVERAGE_OFFSET + 4]
+        .copy_from_slice(&featured.leverage.to_be_bytes());
+    body[FEATURED_COLLATERAL_OFFSET..FEATURED_COLLATERAL_OFFSET + 8]
+        .copy_from_slice(&featured.collateral_micros.to_be_bytes());
+    body[FEATURED_ENTRY_PRICE_OFFSET..FEATURED_ENTRY_PRICE_OFFSET + 16]
+        .copy_from_slice(&featured.entry_price.to_be_bytes());
+    body[FEATURED_IS_OPEN_OFFSET] = featured.is_open;
+    body[FEATURED_TIMESTAMP_OFFSET..FEATURED_TIMESTAMP_OFFSET + 8]
+        .copy_from_slice(&featured.timestamp.to_be_bytes());
+
+    body[GROSS_PNL_OFFSET..GROSS_PNL_OFFSET + 8]
+        .copy_from_slice(&metrics.gross_pnl_micros.to_be_bytes());
+    body[TOTAL_COST_OFFSET..TOTAL_COST_OFFSET + 8]
+        .copy_from_slice(&metrics.total_cost_micros.to_be_bytes());
+    body[MAX_DRAWDOWN_OFFSET..MAX_DRAWDOWN_OFFSET + 8]
+        .copy_from_slice(&metrics.max_drawdown_micros.to_be_bytes());
+    body[SUM_SQ_RETURNS_OFFSET..SUM_SQ_RETURNS_OFFSET + 16]
+        .copy_from_slice(&metrics.sum_sq_returns_micros.to_be_bytes());
+
+    out
+}
+
+/// Decode committed bytes, dispatching on the leading schema version byte.
+pub fn decode(bytes: &[u8]) -> Result<(Metrics, Featured), DecodeError> {
+    let version = *bytes.first().ok_or(DecodeError::Empty)?;
+    match version {
+        1 => decode_v1(bytes),
+        SCHEMA_VERSION => decode_v2(bytes),
+        other => Err(DecodeError::UnknownVersion(other)),
+    }
+}
+
+fn decode_v1(bytes: &[u8]) -> Result<(Metrics, Featured), DecodeError> {
+    if bytes.len() < TOTAL_SIZE_V1 {
+        return Err(DecodeError::TooShort {
+            version: 1,
+            expected: TOTAL_SIZE_V1,
+            actual: bytes.len(),
+        });
+    }
+    let body = &bytes[VERSION_SIZE..TOTAL_SIZE_V1];
+
+    let mut trader = [0u8; 20];
+    trader.copy_from_slice(&body[TRADER_OFFSET..TRADER_OFFSET + 20]);
+
+    let metrics = Metrics {
+        trader,
+        trade_count: u32::from_be_bytes(
+            body[TRADE_COUNT_OFFSET..TRADE_COUNT_OFFSET + 4].try_into().unwrap(),
+        ),
+        win_count: u32::from_be_bytes(body[WIN_COUNT_OFFSET..WIN_COUNT_OFFSET + 4].try_into().unwrap()),
+        total_pnl_micros: i64::from_be_bytes(
+            body[TOTAL_PNL_OFFSET..TOTAL_PNL_OFFSET + 8].try_into().unwrap(),
+        ),
+        total_collateral_micros: u64::from_be_bytes(
+            body[TOTAL_COLLATERAL_OFFSET..TOTAL_COLLATERAL_OFFSET + 8].try_into().unwrap(),
+        ),
+        start_timestamp: u64::from_be_bytes(
+            body[START_TIMESTAMP_OFFSET..START_TIMESTAMP_OFFSET + 8].try_into().unwrap(),
+        ),
+        end_timestamp: u64::from_be_bytes(
+            body[END_TIMESTAMP_OFFSET..END_TIMESTAMP_OFFSET + 8].try_into().unwrap(),
+        ),
+        gross_pnl_micros: 0,
+        total_cost_micros: 0,
+        max_drawdown_micros: 0,
+        sum_sq_returns_micros: 0,
+    };
+
+    let pair_index = u32::from_be_bytes(
+        body[FEATURED_PAIR_INDEX_OFFSET..FEATURED_PAIR_INDEX_OFFSET + 4].try_into().unwrap(),
+    );
+    let side_byte = body[FEATURED_IS_BUY_OFFSET];
+
+    let featured = Featured {
+        trade_id: u64::from_be_bytes(
+            body[FEATURED_TRADE_ID_OFFSET..FEATURED_TRADE_ID_OFFSET + 8].try_into().unwrap(),
+        ),
+        pair_index: PairIndex::try_from(pair_index)?,
+        side: Side::try_from(side_byte)?,
+        leverage: u32::from_be_bytes(
+            body[FEATURED_LEVERAGE_OFFSET..FEATURED_LEVERAGE_OFFSET + 4].try_into().unwrap(),
+        ),
+        collateral_micros: u64::from_be_bytes(
+            body[FEATURED_COLLATERAL_OFFSET..FEATURED_COLLATERAL_OFFSET + 8].try_into().unwrap(),
+        ),
+        entry_price: u128::from_be_bytes(
+            body[FEATURED_ENTRY_PRICE_OFFSET..FEATURED_ENTRY_PRICE_OFFSET + 16].try_into().unwrap(),
+        ),
+        is_open: body[FEATURED_IS_OPEN_OFFSET],
+        timestamp: u64::from_be_bytes(
+            body[FEATURED_TIMESTAMP_OFFSET..FEATURED_TIMESTAMP_OFFSET + 8].try_into().unwrap(),
+        ),
+    };
+
+    Ok((metrics, featured))
+}
+
+fn decode_v2(bytes: &[u8]) -> Result<(Metrics, Featured), DecodeError> {
+    if bytes.len() < TOTAL_SIZE_V2 {
+        return Err(DecodeError::TooShort {
+            version: SCHEMA_VERSION,
+            expected: TOTAL_SIZE_V2,
+            actual: bytes.len(),
+        });
+    }
+    let body = &bytes[VERSION_SIZE..TOTAL_SIZE_V2];
+
+    let (mut metrics, featured) = decode_v1(bytes)?;
+
+    metrics.gross_pnl_micros = i64::from_be_bytes(
+        body[GROSS_PNL_OFFSET..GROSS_PNL_OFFSET + 8].try_into().unwrap(),
+    );
+    metrics.total_cost_micros = u64::from_be_bytes(
+        body[TOTAL_COST_OFFSET..TOTAL_COST_OFFSET + 8].try_into().unwrap(),
+    );
+    metrics.max_drawdown_micros = u64::from_be_bytes(
+        body[MAX_DRAWDOWN_OFFSET..MAX_DRAWDOWN_OFFSET + 8].try_into().unwrap(),
+    );
+    metrics.sum_sq_returns_micros = u128::from_be_bytes(
+        body[SUM_SQ_RETURNS_OFFSET..SUM_SQ_RETURNS_OFFSET + 16].try_into().unwrap(),
+    );
+
+    Ok((metrics, featured))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_metrics() -> Metrics {
+        Metrics {
+            trader: [0x11; 20],
+            trade_count: 7,
+            win_count: 4,
+            total_pnl_micros: -1_234_567,
+            total_collateral_micros: 9_876_543,
+            start_timestamp: 1_700_000_000,
+            end_timestamp: 1_700_001_000,
+            gross_pnl_micros: 2_000_000,
+            total_cost_micros: 3_234_567,
+            max_drawdown_micros: 555_555,
+            sum_sq_returns_micros: 123_456_789_012_345,
+        }
+    }
+
+    fn sample_featured() -> Featured {
+        Featured {
+            trade_id: 42,
+            pair_index: PairIndex::try_from(3).unwrap(),
+            side: Side::Sell,
+            leverage: 500,
+            collateral_micros: 1_000_000,
+            entry_price: 42_000_000_000_000_000_000,
+            is_open: 1,
+            timestamp: 1_700_000_500,
+        }
+    }
+
+    #[test]
+    fn encode_decode_round_trips() {
+        let metrics = sample_metrics();
+        let featured = sample_featured();
+
+        let bytes = encode(&metrics, &featured);
+        let (decoded_metrics, decoded_featured) = decode(&bytes).unwrap();
+
+        assert_eq!(decoded_metrics, metrics);
+        assert_eq!(decoded_featured, featured);
+    }
+
+    #[test]
+    fn decode_rejects_unknown_version() {
+        let mut bytes = encode(&sample_metrics(), &sample_featured()).to_vec();
+        bytes[0] = 99;
+        assert_eq!(decode(&bytes), Err(DecodeError::UnknownVersion(99)));
+    }
+
+    #[test]
+    fn decode_rejects_empty_input() {
+        assert_eq!(decode(&[]), Err(DecodeError::Empty));
+    }
+
+    #[test]
+    fn decode_v1_fills_v2_fields_with_zero() {
+        // A v1-shaped proof (no risk-metrics tail) should still decode, with
+        // the fields it never committed defaulting to zero.
+        let bytes = &encode(&sample_metrics(), &sample_featured())[..TOTAL_SIZE_V1];
+        let mut v1_bytes = bytes.to_vec();
+        v1_bytes[0] = 1;
+
+        let (metrics, _) = decode(&v1_bytes).unwrap();
+        assert_eq!(metrics.gross_pnl_micros, 0);
+        assert_eq!(metrics.total_cost_micros, 0);
+        assert_eq!(metrics.max_drawdown_micros, 0);
+        assert_eq!(metrics.sum_sq_returns_micros, 0);
+    }
+}
@@ -0,0 +1,142 @@
+//! Validated trade direction and market pair index, shared by the guest and host.
+//!
+//! Before this module, `featured_pair_index` was committed as a raw `u32`
+//! and `is_buy` as an untyped byte — nothing stopped a malformed input from
+//! committing a proof over a pair index that doesn't correspond to any real
+//! market. `Side` and `PairIndex` make that state unrepresentable: both
+//! validate on construction (via `TryFrom`), so the invalid value is
+//! rejected at the boundary instead of propagating into a committed proof.
+
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Highest pair index Ostium currently lists. Bump this as new markets launch.
+pub const MAX_PAIR_INDEX: u32 = 255;
+
+/// A market pair index, guaranteed to be within `0..=MAX_PAIR_INDEX`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "u32", into = "u32")]
+pub struct PairIndex(u32);
+
+impl PairIndex {
+    pub fn get(self) -> u32 {
+        self.0
+    }
+}
+
+impl TryFrom<u32> for PairIndex {
+    type Error = InvalidPairIndex;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        if value <= MAX_PAIR_INDEX {
+            Ok(PairIndex(value))
+        } else {
+            Err(InvalidPairIndex(value))
+        }
+    }
+}
+
+impl From<PairIndex> for u32 {
+    fn from(pair_index: PairIndex) -> u32 {
+        pair_index.0
+    }
+}
+
+/// A pair index outside `0..=MAX_PAIR_INDEX`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidPairIndex(pub u32);
+
+impl fmt::Display for InvalidPairIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pair index {} is out of range (max is {MAX_PAIR_INDEX})", self.0)
+    }
+}
+
+impl std::error::Error for InvalidPairIndex {}
+
+/// Trade direction. On the wire (committed bytes, packed trade records) this
+/// is a single byte: `1` = buy, `0` = sell — any other byte is rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    pub fn from_bool(is_buy: bool) -> Self {
+        if is_buy {
+            Side::Buy
+        } else {
+            Side::Sell
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Side::Buy => 1,
+            Side::Sell => 0,
+        }
+    }
+}
+
+impl TryFrom<u8> for Side {
+    type Error = InvalidSideCode;
+
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        match byte {
+            1 => Ok(Side::Buy),
+            0 => Ok(Side::Sell),
+            other => Err(InvalidSideCode(other)),
+        }
+    }
+}
+
+/// A side byte that's neither `0` nor `1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidSideCode(pub u8);
+
+impl fmt::Display for InvalidSideCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unrecognized side code: {} (expected 0 or 1)", self.0)
+    }
+}
+
+impl std::error::Error for InvalidSideCode {}
+
+/// `#[serde(with = "side_as_bool")]` helper for JSON call sites (e.g. the
+/// Ostium subgraph's `is_buy` boolean) that should deserialize straight into
+/// a [`Side`] instead of carrying a separate untyped bool field.
+pub mod side_as_bool {
+    use super::Side;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(side: &Side, serializer: S) -> Result<S::Ok, S::Error> {
+        matches!(side, Side::Buy).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Side, D::Error> {
+        Ok(Side::from_bool(bool::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pair_index_accepts_the_boundary_and_rejects_past_it() {
+        assert_eq!(PairIndex::try_from(0).unwrap().get(), 0);
+        assert_eq!(PairIndex::try_from(MAX_PAIR_INDEX).unwrap().get(), MAX_PAIR_INDEX);
+        assert_eq!(PairIndex::try_from(MAX_PAIR_INDEX + 1), Err(InvalidPairIndex(MAX_PAIR_INDEX + 1)));
+    }
+
+    #[test]
+    fn side_round_trips_through_its_wire_byte() {
+        assert_eq!(Side::try_from(1).unwrap(), Side::Buy);
+        assert_eq!(Side::try_from(0).unwrap(), Side::Sell);
+        assert_eq!(Side::Buy.to_u8(), 1);
+        assert_eq!(Side::Sell.to_u8(), 0);
+        assert_eq!(Side::try_from(2), Err(InvalidSideCode(2)));
+    }
+}
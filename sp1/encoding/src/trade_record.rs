@@ -0,0 +1,101 @@
+//! Fixed-width binary trade record, shared by the host's mmap reader and the
+//! guest's `--input-format binary` path.
+//!
+//! Modeled on the data-pipelines team's packed trade row: a flat, fixed-width
+//! record with no string parsing, so the guest can build a [`PackedTrade`]
+//! straight out of an mmap'd byte slice instead of paying for `str::parse`
+//! on nine JSON string fields per trade.
+//!
+//! Unlike the JSON `Trade`, a record carries no trader address (the trader is
+//! already known from the featured position) and prices are fixed-point at 6
+//! decimals rather than 18 — the PnL formula only ever uses a price *ratio*,
+//! so the lower precision doesn't change which trades win or lose, only the
+//! last couple of decimal digits of the committed total. `funding` and
+//! `rollover` are pre-combined into a single `cost_micros` field, since the
+//! guest only ever needs `funding.abs() + rollover.abs()`.
+//!
+//! On-wire format per record, all integers little-endian:
+//!   [0]      side         (u8, 1 = buy, 0 = sell — see `markets::Side`)
+//!   [1..3)   leverage_x100 (u16)
+//!   [3..11)  collateral_micros (u64)
+//!   [11..19) open_price_micros (u64)
+//!   [19..27) close_price_micros (u64)
+//!   [27..31) cost_micros  (u32) — abs(funding) + abs(rollover), in micros
+//!   [31..39) timestamp    (u64)
+//!   [39]     reserved (written as 0 by `to_bytes`; not checked by `from_bytes`)
+
+use std::convert::{TryFrom, TryInto};
+
+use crate::Side;
+
+pub const IS_BUY_OFFSET: usize = 0;
+pub const LEVERAGE_OFFSET: usize = 1;
+pub const COLLATERAL_OFFSET: usize = 3;
+pub const OPEN_PRICE_OFFSET: usize = 11;
+pub const CLOSE_PRICE_OFFSET: usize = 19;
+pub const COST_OFFSET: usize = 27;
+pub const TIMESTAMP_OFFSET: usize = 31;
+pub const RESERVED_OFFSET: usize = 39;
+
+/// Size in bytes of one packed trade record.
+pub const RECORD_SIZE: usize = 40;
+
+/// One closed trade, decoded straight from a fixed-width binary record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PackedTrade {
+    pub side: Side,
+    pub leverage_x100: u16,
+    pub collateral_micros: u64,
+    pub open_price_micros: u64,
+    pub close_price_micros: u64,
+    pub cost_micros: u32,
+    pub timestamp: u64,
+}
+
+impl PackedTrade {
+    /// Parse a single `RECORD_SIZE`-byte record. Panics if `bytes` isn't
+    /// exactly `RECORD_SIZE` long — callers are expected to have already
+    /// validated `buffer.len() % RECORD_SIZE == 0` and be chunking by it —
+    /// or if the side byte isn't a recognized `0`/`1` code, since a garbage
+    /// direction can't be silently cast into a committed proof.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        assert_eq!(bytes.len(), RECORD_SIZE, "trade record must be {RECORD_SIZE} bytes");
+
+        Self {
+            side: Side::try_from(bytes[IS_BUY_OFFSET]).expect("invalid side code in packed trade record"),
+            leverage_x100: u16::from_le_bytes(
+                bytes[LEVERAGE_OFFSET..LEVERAGE_OFFSET + 2].try_into().unwrap(),
+            ),
+            collateral_micros: u64::from_le_bytes(
+                bytes[COLLATERAL_OFFSET..COLLATERAL_OFFSET + 8].try_into().unwrap(),
+            ),
+            open_price_micros: u64::from_le_bytes(
+                bytes[OPEN_PRICE_OFFSET..OPEN_PRICE_OFFSET + 8].try_into().unwrap(),
+            ),
+            close_price_micros: u64::from_le_bytes(
+                bytes[CLOSE_PRICE_OFFSET..CLOSE_PRICE_OFFSET + 8].try_into().unwrap(),
+            ),
+            cost_micros: u32::from_le_bytes(bytes[COST_OFFSET..COST_OFFSET + 4].try_into().unwrap()),
+            timestamp: u64::from_le_bytes(
+                bytes[TIMESTAMP_OFFSET..TIMESTAMP_OFFSET + 8].try_into().unwrap(),
+            ),
+        }
+    }
+
+    /// Pack this trade into an on-wire record, for the host to write to disk.
+    pub fn to_bytes(&self) -> [u8; RECORD_SIZE] {
+        let mut out = [0u8; RECORD_SIZE];
+        out[IS_BUY_OFFSET] = self.side.to_u8();
+        out[LEVERAGE_OFFSET..LEVERAGE_OFFSET + 2].copy_from_slice(&self.leverage_x100.to_le_bytes());
+        out[COLLATERAL_OFFSET..COLLATERAL_OFFSET + 8]
+            .copy_from_slice(&self.collateral_micros.to_le_bytes());
+        out[OPEN_PRICE_OFFSET..OPEN_PRICE_OFFSET + 8]
+            .copy_from_slice(&self.open_price_micros.to_le_bytes());
+        out[CLOSE_PRICE_OFFSET..CLOSE_PRICE_OFFSET + 8]
+            .copy_from_slice(&self.close_price_micros.to_le_bytes());
+        out[COST_OFFSET..COST_OFFSET + 4].copy_from_slice(&self.cost_micros.to_le_bytes());
+        out[TIMESTAMP_OFFSET..TIMESTAMP_OFFSET + 8].copy_from_slice(&self.timestamp.to_le_bytes());
+        // out[RESERVED_OFFSET] stays 0
+        out
+    }
+}
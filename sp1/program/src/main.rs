@@ -5,38 +5,34 @@
 //! 2. A specific featured position (the one being listed as alpha)
 //!
 //! Inputs (read from host):
-//!   - Vec<Trade>         — closed trade data from Ostium subgraph
+//!   - TradesInput        — closed trade data from Ostium subgraph, either as
+//!                          `Vec<Trade>` JSON or a packed binary record buffer
 //!   - FeaturedPosition   — the open position to prove alongside performance
 //!
-//! Outputs (committed to public, ALL BIG-ENDIAN for Solidity compatibility):
-//!   — Aggregate (60 bytes):
-//!     - trader: [u8; 20]       (20B)
-//!     - trade_count: u32       (4B)
-//!     - win_count: u32         (4B)
-//!     - total_pnl: i64         (8B) — PnL in USDC micros (6 dec)
-//!     - total_collateral: u64  (8B) — collateral in USDC micros (6 dec)
-//!     - start_timestamp: u64   (8B)
-//!     - end_timestamp: u64     (8B)
-//!   — Featured position (50 bytes):
-//!     - featured_trade_id: u64   (8B)
-//!     - featured_pair_index: u32 (4B)
-//!     - featured_is_buy: u8      (1B)
-//!     - featured_leverage: u32   (4B) — leverage × 100
-//!     - featured_collateral: u64 (8B) — USDC micros (6 dec)
-//!     - featured_entry_price: u128 (16B) — 18 decimals
-//!     - featured_is_open: u8     (1B)
-//!     - featured_timestamp: u64  (8B)
+//! Outputs (committed to public, ALL BIG-ENDIAN for Solidity compatibility): the
+//! byte layout is owned by the `encoding` crate, shared with the host, so it's
+//! defined once instead of mirrored here in comments.
+//!
+//! Trades must arrive sorted by ascending timestamp: max drawdown is computed
+//! over the timestamp-ordered equity curve in a single pass, so the host is
+//! responsible for sorting (both the JSON trade list and the binary record
+//! file) before handing trades to the guest. `Aggregates::accumulate` asserts
+//! this invariant rather than re-sorting, so the binary ingestion path stays
+//! allocation-free.
 
 #![no_main]
 sp1_zkvm::entrypoint!(main);
 
+use encoding::trade_record::{PackedTrade, RECORD_SIZE};
+use encoding::{Featured as EncodedFeatured, Metrics as EncodedMetrics, PairIndex, Side};
 use serde::{Deserialize, Serialize};
 
-/// A single closed trade from the Ostium subgraph.
+/// A single closed trade from the Ostium subgraph, as sent over JSON.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Trade {
     pub trader: String,
-    pub is_buy: bool,
+    #[serde(rename = "is_buy", with = "encoding::side_as_bool")]
+    pub side: Side,
     pub collateral: String,   // 6 decimals (USDC)
     pub leverage: String,     // 2 decimals (e.g. 5000 = 50x)
     pub open_price: String,   // 18 decimals
@@ -46,13 +42,26 @@ pub struct Trade {
     pub rollover: String,     // 18 decimals
 }
 
+/// The trade history handed to the guest, in whichever format the host chose.
+///
+/// `Binary` holds the raw bytes of a `--input-format binary` file, still
+/// packed as `encoding::trade_record::PackedTrade` records — decoded lazily
+/// in the aggregate loop instead of up front, so there's no intermediate
+/// `Vec<PackedTrade>` allocation for large histories.
+#[derive(Serialize, Deserialize, Debug)]
+pub enum TradesInput {
+    Json(Vec<Trade>),
+    Binary(Vec<u8>),
+}
+
 /// The featured open position being listed as alpha.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FeaturedPosition {
     pub trader: String,
     pub trade_id: u64,
-    pub pair_index: u32,
-    pub is_buy: bool,
+    pub pair_index: PairIndex,
+    #[serde(rename = "is_buy", with = "encoding::side_as_bool")]
+    pub side: Side,
     pub leverage: String,     // 2 decimals (e.g. 500 = 5x)
     pub collateral: String,   // 6 decimals (USDC)
     pub entry_price: String,  // 18 decimals
@@ -63,10 +72,53 @@ pub struct FeaturedPosition {
 /// Combined input from host
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ProofInput {
-    pub trades: Vec<Trade>,
+    pub trades: TradesInput,
     pub featured: FeaturedPosition,
 }
 
+/// Per-trade fields needed by the aggregate loop, independent of whether the
+/// trade came from a JSON `Trade` or a binary `PackedTrade`.
+struct TradeFields {
+    side: Side,
+    collateral_micros: u64,
+    leverage_x100: u128,
+    open_price: u128,
+    close_price: u128,
+    cost_micros: i64,
+    timestamp: u64,
+}
+
+impl From<&Trade> for TradeFields {
+    fn from(trade: &Trade) -> Self {
+        let funding_micros = (parse_u128(&trade.funding) / 1_000_000_000_000) as i64;
+        let rollover_micros = (parse_u128(&trade.rollover) / 1_000_000_000_000) as i64;
+
+        Self {
+            side: trade.side,
+            collateral_micros: parse_u128(&trade.collateral) as u64,
+            leverage_x100: parse_u128(&trade.leverage),
+            open_price: parse_u128(&trade.open_price),
+            close_price: parse_u128(&trade.close_price),
+            cost_micros: funding_micros.abs() + rollover_micros.abs(),
+            timestamp: parse_u64(&trade.timestamp),
+        }
+    }
+}
+
+impl From<PackedTrade> for TradeFields {
+    fn from(trade: PackedTrade) -> Self {
+        Self {
+            side: trade.side,
+            collateral_micros: trade.collateral_micros,
+            leverage_x100: trade.leverage_x100 as u128,
+            open_price: trade.open_price_micros as u128,
+            close_price: trade.close_price_micros as u128,
+            cost_micros: trade.cost_micros as i64,
+            timestamp: trade.timestamp,
+        }
+    }
+}
+
 fn parse_u128(s: &str) -> u128 {
     s.parse::<u128>().unwrap_or(0)
 }
@@ -86,73 +138,174 @@ fn decode_address(addr: &str) -> [u8; 20] {
     bytes
 }
 
-pub fn main() {
-    // Read combined input from host
-    let input: ProofInput = sp1_zkvm::io::read();
+/// Running totals built up one [`TradeFields`] at a time, shared by the JSON
+/// and binary ingestion paths.
+///
+/// `running_equity`/`peak_equity` track the timestamp-ordered equity curve
+/// used to derive `max_drawdown_micros`; they're not part of the committed
+/// output themselves, just scratch state for computing it incrementally.
+#[derive(Default)]
+struct Aggregates {
+    trade_count: u32,
+    win_count: u32,
+    total_pnl_micros: i64,
+    total_collateral_micros: u64,
+    start_timestamp: u64,
+    end_timestamp: u64,
+    gross_pnl_micros: i64,
+    total_cost_micros: u64,
+    sum_sq_returns_micros: u128,
+    max_drawdown_micros: u64,
+    running_equity_micros: i64,
+    peak_equity_micros: i64,
+    last_timestamp: u64,
+}
 
-    let trades = input.trades;
-    let featured = input.featured;
+impl Aggregates {
+    fn accumulate(&mut self, trade: TradeFields) {
+        if self.trade_count == 0 {
+            self.start_timestamp = u64::MAX;
+        } else {
+            assert!(
+                trade.timestamp >= self.last_timestamp,
+                "trades must be pre-sorted by ascending timestamp for drawdown to be meaningful"
+            );
+        }
+        self.last_timestamp = trade.timestamp;
+        self.trade_count += 1;
+        self.total_collateral_micros += trade.collateral_micros;
 
-    // ========================================================================
-    // Part 1: Aggregate performance metrics (from closed trades)
-    // ========================================================================
+        let mut gross_pnl_micros: i64 = 0;
 
-    let mut trade_count: u32 = 0;
-    let mut win_count: u32 = 0;
-    let mut total_pnl_micros: i64 = 0;
-    let mut total_collateral_micros: u64 = 0;
-    let mut start_timestamp: u64 = u64::MAX;
-    let mut end_timestamp: u64 = 0;
-    let mut trader_bytes = [0u8; 20];
+        if trade.open_price > 0 {
+            let price_diff = match trade.side {
+                Side::Buy => trade.close_price as i128 - trade.open_price as i128,
+                Side::Sell => trade.open_price as i128 - trade.close_price as i128,
+            };
 
-    for trade in &trades {
-        if trade_count == 0 {
-            trader_bytes = decode_address(&trade.trader);
+            let numerator = trade.collateral_micros as i128 * trade.leverage_x100 as i128 * price_diff;
+            let denominator = trade.open_price as i128 * 100;
+            gross_pnl_micros = (numerator / denominator) as i64;
         }
 
-        trade_count += 1;
-
-        let collateral_micros = parse_u128(&trade.collateral);
-        total_collateral_micros += collateral_micros as u64;
+        self.gross_pnl_micros += gross_pnl_micros;
+        self.total_cost_micros += trade.cost_micros.unsigned_abs();
 
-        let open_price = parse_u128(&trade.open_price);
-        let close_price = parse_u128(&trade.close_price);
-        let leverage = parse_u128(&trade.leverage);
+        let trade_pnl_micros = gross_pnl_micros - trade.cost_micros;
 
-        let mut trade_pnl_micros: i64 = 0;
+        self.total_pnl_micros += trade_pnl_micros;
+        self.sum_sq_returns_micros += (trade_pnl_micros as i128).pow(2) as u128;
+        if trade_pnl_micros > 0 {
+            self.win_count += 1;
+        }
 
-        if open_price > 0 {
-            let price_diff = if trade.is_buy {
-                close_price as i128 - open_price as i128
-            } else {
-                open_price as i128 - close_price as i128
-            };
+        self.running_equity_micros += trade_pnl_micros;
+        if self.running_equity_micros > self.peak_equity_micros {
+            self.peak_equity_micros = self.running_equity_micros;
+        }
+        let drawdown_micros = (self.peak_equity_micros - self.running_equity_micros) as u64;
+        if drawdown_micros > self.max_drawdown_micros {
+            self.max_drawdown_micros = drawdown_micros;
+        }
 
-            let numerator = collateral_micros as i128 * leverage as i128 * price_diff;
-            let denominator = open_price as i128 * 100;
-            trade_pnl_micros = (numerator / denominator) as i64;
+        if trade.timestamp < self.start_timestamp {
+            self.start_timestamp = trade.timestamp;
+        }
+        if trade.timestamp > self.end_timestamp {
+            self.end_timestamp = trade.timestamp;
         }
+    }
+}
 
-        let funding_micros = (parse_u128(&trade.funding) / 1_000_000_000_000) as i64;
-        let rollover_micros = (parse_u128(&trade.rollover) / 1_000_000_000_000) as i64;
-        trade_pnl_micros -= funding_micros.abs() + rollover_micros.abs();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        total_pnl_micros += trade_pnl_micros;
-        if trade_pnl_micros > 0 {
-            win_count += 1;
+    fn trade(side: Side, open_price: u128, close_price: u128, cost_micros: i64, timestamp: u64) -> TradeFields {
+        TradeFields {
+            side,
+            collateral_micros: 1_000_000,
+            leverage_x100: 200,
+            open_price,
+            close_price,
+            cost_micros,
+            timestamp,
         }
+    }
+
+    #[test]
+    fn accumulate_tracks_pnl_split_and_drawdown_across_trades() {
+        let mut agg = Aggregates::default();
+
+        // A winning buy, then a losing sell with a cost — in ascending
+        // timestamp order, as the guest requires.
+        agg.accumulate(trade(Side::Buy, 100, 110, 0, 100));
+        agg.accumulate(trade(Side::Sell, 100, 120, 50_000, 200));
+
+        assert_eq!(agg.trade_count, 2);
+        assert_eq!(agg.win_count, 1);
+        assert_eq!(agg.total_collateral_micros, 2_000_000);
+        assert_eq!(agg.start_timestamp, 100);
+        assert_eq!(agg.end_timestamp, 200);
+
+        assert_eq!(agg.gross_pnl_micros, -200_000);
+        assert_eq!(agg.total_cost_micros, 50_000);
+        assert_eq!(agg.total_pnl_micros, -250_000);
+        assert_eq!(agg.sum_sq_returns_micros, 242_500_000_000);
+
+        // Equity peaks at +200_000 after the first trade, then drops to
+        // -250_000 after the second — a 450_000 peak-to-trough drawdown.
+        assert_eq!(agg.max_drawdown_micros, 450_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "pre-sorted by ascending timestamp")]
+    fn accumulate_rejects_out_of_order_timestamps() {
+        let mut agg = Aggregates::default();
+        agg.accumulate(trade(Side::Buy, 100, 110, 0, 200));
+        agg.accumulate(trade(Side::Buy, 100, 110, 0, 100));
+    }
+}
 
-        let ts = parse_u64(&trade.timestamp);
-        if ts < start_timestamp {
-            start_timestamp = ts;
+pub fn main() {
+    // Read combined input from host
+    let input: ProofInput = sp1_zkvm::io::read();
+
+    let trades = input.trades;
+    let featured = input.featured;
+
+    // ========================================================================
+    // Part 1: Aggregate performance metrics (from closed trades)
+    // ========================================================================
+
+    let mut agg = Aggregates::default();
+    let mut trader_bytes = [0u8; 20];
+
+    let has_trades = match &trades {
+        TradesInput::Json(trades) => {
+            if let Some(first) = trades.first() {
+                trader_bytes = decode_address(&first.trader);
+            }
+            for trade in trades {
+                agg.accumulate(TradeFields::from(trade));
+            }
+            !trades.is_empty()
         }
-        if ts > end_timestamp {
-            end_timestamp = ts;
+        TradesInput::Binary(bytes) => {
+            assert_eq!(bytes.len() % RECORD_SIZE, 0, "binary trade input is not record-aligned");
+            // Packed records carry no trader field (it's already known from the
+            // featured position — see trade_record.rs), so it's always sourced
+            // from there, not just in the no-trades fallback below.
+            trader_bytes = decode_address(&featured.trader);
+            for chunk in bytes.chunks_exact(RECORD_SIZE) {
+                agg.accumulate(TradeFields::from(PackedTrade::from_bytes(chunk)));
+            }
+            !bytes.is_empty()
         }
-    }
+    };
 
-    if trades.is_empty() {
-        start_timestamp = 0;
+    if !has_trades {
+        agg.start_timestamp = 0;
         // If no closed trades, use featured position's trader
         trader_bytes = decode_address(&featured.trader);
     }
@@ -163,7 +316,7 @@ pub fn main() {
 
     let featured_trade_id = featured.trade_id;
     let featured_pair_index = featured.pair_index;
-    let featured_is_buy: u8 = if featured.is_buy { 1 } else { 0 };
+    let featured_side = featured.side;
     let featured_leverage = parse_u128(&featured.leverage) as u32;
     let featured_collateral_micros = parse_u128(&featured.collateral) as u64;
     let featured_entry_price = parse_u128(&featured.entry_price);
@@ -171,25 +324,33 @@ pub fn main() {
     let featured_timestamp = parse_u64(&featured.timestamp);
 
     // ========================================================================
-    // Commit all values in BIG-ENDIAN for Solidity compatibility
+    // Commit the versioned, shared-layout public values
     // ========================================================================
 
-    // Aggregate metrics (60 bytes)
-    sp1_zkvm::io::commit_slice(&trader_bytes);                          // 20 bytes
-    sp1_zkvm::io::commit_slice(&trade_count.to_be_bytes());             // 4 bytes
-    sp1_zkvm::io::commit_slice(&win_count.to_be_bytes());               // 4 bytes
-    sp1_zkvm::io::commit_slice(&total_pnl_micros.to_be_bytes());        // 8 bytes
-    sp1_zkvm::io::commit_slice(&total_collateral_micros.to_be_bytes()); // 8 bytes
-    sp1_zkvm::io::commit_slice(&start_timestamp.to_be_bytes());         // 8 bytes
-    sp1_zkvm::io::commit_slice(&end_timestamp.to_be_bytes());           // 8 bytes
-
-    // Featured position (50 bytes)
-    sp1_zkvm::io::commit_slice(&featured_trade_id.to_be_bytes());       // 8 bytes
-    sp1_zkvm::io::commit_slice(&featured_pair_index.to_be_bytes());     // 4 bytes
-    sp1_zkvm::io::commit_slice(&[featured_is_buy]);                     // 1 byte
-    sp1_zkvm::io::commit_slice(&featured_leverage.to_be_bytes());       // 4 bytes
-    sp1_zkvm::io::commit_slice(&featured_collateral_micros.to_be_bytes()); // 8 bytes
-    sp1_zkvm::io::commit_slice(&featured_entry_price.to_be_bytes());    // 16 bytes
-    sp1_zkvm::io::commit_slice(&[featured_is_open]);                    // 1 byte
-    sp1_zkvm::io::commit_slice(&featured_timestamp.to_be_bytes());      // 8 bytes
+    let metrics = EncodedMetrics {
+        trader: trader_bytes,
+        trade_count: agg.trade_count,
+        win_count: agg.win_count,
+        total_pnl_micros: agg.total_pnl_micros,
+        total_collateral_micros: agg.total_collateral_micros,
+        start_timestamp: agg.start_timestamp,
+        end_timestamp: agg.end_timestamp,
+        gross_pnl_micros: agg.gross_pnl_micros,
+        total_cost_micros: agg.total_cost_micros,
+        max_drawdown_micros: agg.max_drawdown_micros,
+        sum_sq_returns_micros: agg.sum_sq_returns_micros,
+    };
+
+    let featured = EncodedFeatured {
+        trade_id: featured_trade_id,
+        pair_index: featured_pair_index,
+        side: featured_side,
+        leverage: featured_leverage,
+        collateral_micros: featured_collateral_micros,
+        entry_price: featured_entry_price,
+        is_open: featured_is_open,
+        timestamp: featured_timestamp,
+    };
+
+    sp1_zkvm::io::commit_slice(&encoding::encode(&metrics, &featured));
 }
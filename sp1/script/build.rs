@@ -0,0 +1,9 @@
+//! Emits CUDA link directives only when the `cuda` feature is enabled, so a
+//! plain `cargo build` (no features) stays free of any CUDA toolkit dependency.
+
+fn main() {
+    if std::env::var_os("CARGO_FEATURE_CUDA").is_some() {
+        println!("cargo:rustc-link-lib=dylib=cudart");
+        println!("cargo:rustc-link-search=native=/usr/local/cuda/lib64");
+    }
+}
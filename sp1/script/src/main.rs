@@ -1,23 +1,40 @@
 //! Ostium Trader Proof — SP1 Host Script
 //!
 //! This host program:
-//! 1. Reads combined input (trades + featured position) as JSON from stdin
+//! 1. Reads the featured position (and, in JSON mode, the trade history too)
+//!    from stdin, and the trade history itself from either that JSON or a
+//!    memory-mapped binary file
 //! 2. Executes the guest program in SP1's zkVM
 //! 3. Generates a proof (Groth16 for on-chain, or mock for testing)
 //! 4. Outputs the proof and public values as JSON to stdout
 //!
-//! Input JSON format:
-//!   { "trades": [...], "featured": { ... } }
+//! Input formats:
+//!   --input-format json (default): stdin is `{ "trades": [...], "featured": { ... } }`
+//!   --input-format binary: stdin is `{ "featured": { ... } }`, and `--input-file`
+//!     points at a file of fixed-width `encoding::trade_record::PackedTrade`
+//!     records, which is memory-mapped and streamed into the guest unparsed —
+//!     see that module for the on-disk layout and why it's faster than JSON.
 //!
 //! Usage:
 //!   echo '<input_json>' | cargo run --release -- --mode execute
 //!   echo '<input_json>' | cargo run --release -- --mode prove
+//!   echo '{"featured": {...}}' | cargo run --release -- --mode execute \
+//!       --input-format binary --input-file trades.bin
+//!
+//! The guest's max-drawdown computation needs trades in ascending timestamp
+//! order and enforces that invariant rather than re-sorting, so this host
+//! sorts both the JSON trade list and the binary record buffer before they're
+//! written to the guest's stdin.
 
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 use sp1_sdk::{include_elf, HashableKey, ProverClient, SP1Stdin};
+use std::fs::File;
 use std::io::Read;
 
+use encoding::trade_record::{PackedTrade, RECORD_SIZE};
+use encoding::{DecodeError, PairIndex, Side};
+
 /// The ELF binary of the compiled guest program
 const GUEST_ELF: &[u8] = include_elf!("ostium-trader-proof");
 
@@ -27,13 +44,44 @@ struct Args {
     /// Proving mode: "execute" (fast, no proof) or "prove" (full ZK proof)
     #[arg(long, default_value = "execute")]
     mode: String,
+
+    /// Trade ingestion format: "json" (trades come from stdin) or "binary"
+    /// (trades are memory-mapped from `--input-file`)
+    #[arg(long, default_value = "json")]
+    input_format: String,
+
+    /// Path to a file of fixed-width packed trade records. Required when
+    /// `--input-format binary` is used.
+    #[arg(long)]
+    input_file: Option<String>,
+
+    /// Proving backend: "cpu", "cuda" (requires building with `--features cuda`),
+    /// "network" (SP1 prover network), or "mock" (no real proving, for local
+    /// testing — public values are still produced, but `proof` is not valid)
+    #[arg(long, default_value = "cpu")]
+    prover: String,
+}
+
+/// Build a `ProverClient` for the requested backend.
+fn build_client(prover: &str) -> ProverClient {
+    match prover {
+        "cpu" => ProverClient::builder().cpu().build(),
+        "mock" => ProverClient::builder().mock().build(),
+        "network" => ProverClient::builder().network().build(),
+        #[cfg(feature = "cuda")]
+        "cuda" => ProverClient::builder().cuda().build(),
+        #[cfg(not(feature = "cuda"))]
+        "cuda" => panic!("Built without the `cuda` feature; rebuild with `--features cuda`"),
+        other => panic!("Unknown --prover: {other}. Use 'cpu', 'cuda', 'network', or 'mock'"),
+    }
 }
 
 /// Trade data matching the guest program's Trade struct
 #[derive(Serialize, Deserialize, Debug)]
 struct Trade {
     trader: String,
-    is_buy: bool,
+    #[serde(rename = "is_buy", with = "encoding::side_as_bool")]
+    side: Side,
     collateral: String,
     leverage: String,
     open_price: String,
@@ -48,8 +96,9 @@ struct Trade {
 struct FeaturedPosition {
     trader: String,
     trade_id: u64,
-    pair_index: u32,
-    is_buy: bool,
+    pair_index: PairIndex,
+    #[serde(rename = "is_buy", with = "encoding::side_as_bool")]
+    side: Side,
     leverage: String,
     collateral: String,
     entry_price: String,
@@ -57,18 +106,43 @@ struct FeaturedPosition {
     timestamp: String,
 }
 
+/// Trade history in whichever format the host chose, matching the guest
+/// program's `TradesInput` enum.
+#[derive(Serialize, Deserialize, Debug)]
+enum TradesInput {
+    Json(Vec<Trade>),
+    Binary(Vec<u8>),
+}
+
 /// Combined input matching the guest program's ProofInput struct
 #[derive(Serialize, Deserialize, Debug)]
 struct ProofInput {
+    trades: TradesInput,
+    featured: FeaturedPosition,
+}
+
+/// Shape of stdin when `--input-format json` (the default): the full trade
+/// history travels alongside the featured position.
+#[derive(Deserialize, Debug)]
+struct JsonStdinInput {
     trades: Vec<Trade>,
     featured: FeaturedPosition,
 }
 
+/// Shape of stdin when `--input-format binary`: trades come from
+/// `--input-file` instead, so stdin only carries the featured position.
+#[derive(Deserialize, Debug)]
+struct BinaryStdinInput {
+    featured: FeaturedPosition,
+}
+
 /// JSON output structure
 #[derive(Serialize)]
 struct ProofOutput {
     success: bool,
     mode: String,
+    /// Which prover backend ("cpu", "cuda", "network", or "mock") produced this proof.
+    backend: String,
     metrics: MetricsOutput,
     featured: FeaturedOutput,
     proof: Option<String>,
@@ -86,13 +160,24 @@ struct MetricsOutput {
     total_collateral: f64,
     start_timestamp: u64,
     end_timestamp: u64,
+    /// Raw price-based PnL, before funding/rollover costs.
+    gross_pnl: f64,
+    /// Sum of funding + rollover costs across all trades.
+    total_cost: f64,
+    /// Largest peak-to-trough drop in cumulative net PnL.
+    max_drawdown: f64,
+    /// Mean per-trade net PnL, derived from `total_pnl` / `trade_count`.
+    mean_return: f64,
+    /// Population standard deviation of per-trade net PnL, for a
+    /// Sharpe-like ratio (`mean_return / return_stddev`).
+    return_stddev: f64,
 }
 
 #[derive(Serialize)]
 struct FeaturedOutput {
     trade_id: u64,
     pair_index: u32,
-    is_buy: bool,
+    side: Side,
     leverage: f64,
     collateral: f64,
     entry_price: f64,
@@ -100,23 +185,71 @@ struct FeaturedOutput {
     timestamp: u64,
 }
 
+/// Decode every fixed-width record in `bytes`, sort by ascending timestamp,
+/// and re-pack — the guest requires time-ordered trades for its drawdown
+/// computation but a freshly-exported record file makes no ordering promise.
+fn sort_packed_trades(bytes: &[u8]) -> Vec<u8> {
+    let mut records: Vec<PackedTrade> =
+        bytes.chunks_exact(RECORD_SIZE).map(PackedTrade::from_bytes).collect();
+    records.sort_by_key(|record| record.timestamp);
+
+    let mut out = Vec::with_capacity(bytes.len());
+    for record in &records {
+        out.extend_from_slice(&record.to_bytes());
+    }
+    out
+}
+
 fn main() {
     let args = Args::parse();
 
-    // Read combined input JSON from stdin
     let mut input_str = String::new();
     std::io::stdin().read_to_string(&mut input_str).expect("Failed to read stdin");
-    let input: ProofInput = serde_json::from_str(&input_str).expect("Failed to parse input JSON");
+
+    let (trades, featured, trade_count) = match args.input_format.as_str() {
+        "json" => {
+            let mut parsed: JsonStdinInput =
+                serde_json::from_str(&input_str).expect("Failed to parse input JSON");
+            parsed.trades.sort_by_key(|trade| trade.timestamp.parse::<u64>().unwrap_or(0));
+            let trade_count = parsed.trades.len();
+            (TradesInput::Json(parsed.trades), parsed.featured, trade_count)
+        }
+        "binary" => {
+            let parsed: BinaryStdinInput =
+                serde_json::from_str(&input_str).expect("Failed to parse input JSON");
+            let path = args
+                .input_file
+                .as_deref()
+                .expect("--input-file is required with --input-format binary");
+
+            let file = File::open(path).unwrap_or_else(|e| panic!("Failed to open {path}: {e}"));
+            let mmap = unsafe {
+                memmap2::Mmap::map(&file).unwrap_or_else(|e| panic!("Failed to mmap {path}: {e}"))
+            };
+            assert_eq!(
+                mmap.len() % RECORD_SIZE,
+                0,
+                "{path} is {} bytes, not a multiple of the {RECORD_SIZE}-byte record size",
+                mmap.len()
+            );
+
+            let trade_count = mmap.len() / RECORD_SIZE;
+            (TradesInput::Binary(sort_packed_trades(&mmap)), parsed.featured, trade_count)
+        }
+        other => panic!("Unknown --input-format: {other}. Use 'json' or 'binary'"),
+    };
+
+    let input = ProofInput { trades, featured };
 
     eprintln!(
         "[sp1-host] Processing {} trades + featured position (tradeId={}) in '{}' mode",
-        input.trades.len(),
+        trade_count,
         input.featured.trade_id,
         args.mode
     );
 
-    // Create SP1 prover client
-    let client = ProverClient::from_env();
+    // Create SP1 prover client for the requested backend
+    let client = build_client(&args.prover);
 
     // Prepare stdin for the guest
     let mut stdin = SP1Stdin::new();
@@ -138,11 +271,19 @@ fn main() {
 
             let (_, vk) = client.setup(GUEST_ELF);
 
-            let (metrics_out, featured_out) = decode_public_values(public_bytes);
+            let (metrics_out, featured_out) = match decode_public_values(public_bytes) {
+                Ok(decoded) => decoded,
+                Err(e) => {
+                    eprintln!("[sp1-host] Failed to decode public values: {e}");
+                    println!("{}", serde_json::to_string(&error_output("execute", &args.prover, &e.to_string())).unwrap());
+                    std::process::exit(1);
+                }
+            };
 
             let result = ProofOutput {
                 success: true,
                 mode: "execute".to_string(),
+                backend: args.prover.clone(),
                 metrics: metrics_out,
                 featured: featured_out,
                 proof: None,
@@ -165,7 +306,14 @@ fn main() {
                 .expect("Proving failed");
 
             let public_bytes = proof.public_values.as_ref();
-            let (metrics_out, featured_out) = decode_public_values(public_bytes);
+            let (metrics_out, featured_out) = match decode_public_values(public_bytes) {
+                Ok(decoded) => decoded,
+                Err(e) => {
+                    eprintln!("[sp1-host] Failed to decode public values: {e}");
+                    println!("{}", serde_json::to_string(&error_output("prove", &args.prover, &e.to_string())).unwrap());
+                    std::process::exit(1);
+                }
+            };
 
             // Verify locally before outputting
             client
@@ -179,6 +327,7 @@ fn main() {
             let result = ProofOutput {
                 success: true,
                 mode: "prove".to_string(),
+                backend: args.prover.clone(),
                 metrics: metrics_out,
                 featured: featured_out,
                 proof: Some(hex::encode(&proof_bytes)),
@@ -190,105 +339,94 @@ fn main() {
             println!("{}", serde_json::to_string(&result).unwrap());
         }
         other => {
-            let result = ProofOutput {
-                success: false,
-                mode: other.to_string(),
-                metrics: MetricsOutput {
-                    trader: String::new(),
-                    trade_count: 0,
-                    win_count: 0,
-                    total_pnl: 0.0,
-                    total_collateral: 0.0,
-                    start_timestamp: 0,
-                    end_timestamp: 0,
-                },
-                featured: FeaturedOutput {
-                    trade_id: 0,
-                    pair_index: 0,
-                    is_buy: false,
-                    leverage: 0.0,
-                    collateral: 0.0,
-                    entry_price: 0.0,
-                    is_open: false,
-                    timestamp: 0,
-                },
-                proof: None,
-                public_values: None,
-                vkey_hash: None,
-                error: Some(format!("Unknown mode: {}. Use 'execute' or 'prove'", other)),
-            };
+            let result = error_output(other, &args.prover, &format!("Unknown mode: {}. Use 'execute' or 'prove'", other));
             println!("{}", serde_json::to_string(&result).unwrap());
             std::process::exit(1);
         }
     }
 }
 
-/// Decode the 110-byte big-endian public values committed by the guest.
-///
-/// Layout:
-///   [0..20]   trader address (20 bytes)
-///   [20..24]  trade_count (u32 BE)
-///   [24..28]  win_count (u32 BE)
-///   [28..36]  total_pnl (i64 BE)
-///   [36..44]  total_collateral (u64 BE)
-///   [44..52]  start_timestamp (u64 BE)
-///   [52..60]  end_timestamp (u64 BE)
-///   [60..68]  featured_trade_id (u64 BE)
-///   [68..72]  featured_pair_index (u32 BE)
-///   [72]      featured_is_buy (u8)
-///   [73..77]  featured_leverage (u32 BE)
-///   [77..85]  featured_collateral (u64 BE)
-///   [85..101] featured_entry_price (u128 BE)
-///   [101]     featured_is_open (u8)
-///   [102..110] featured_timestamp (u64 BE)
-fn decode_public_values(bytes: &[u8]) -> (MetricsOutput, FeaturedOutput) {
-    assert!(
-        bytes.len() >= 110,
-        "Public values too short: {} bytes, expected 110",
-        bytes.len()
-    );
+/// Build a failure `ProofOutput` with zeroed-out metrics/featured placeholders.
+fn error_output(mode: &str, backend: &str, error: &str) -> ProofOutput {
+    ProofOutput {
+        success: false,
+        mode: mode.to_string(),
+        backend: backend.to_string(),
+        metrics: MetricsOutput {
+            trader: String::new(),
+            trade_count: 0,
+            win_count: 0,
+            total_pnl: 0.0,
+            total_collateral: 0.0,
+            start_timestamp: 0,
+            end_timestamp: 0,
+            gross_pnl: 0.0,
+            total_cost: 0.0,
+            max_drawdown: 0.0,
+            mean_return: 0.0,
+            return_stddev: 0.0,
+        },
+        featured: FeaturedOutput {
+            trade_id: 0,
+            pair_index: 0,
+            side: Side::Sell,
+            leverage: 0.0,
+            collateral: 0.0,
+            entry_price: 0.0,
+            is_open: false,
+            timestamp: 0,
+        },
+        proof: None,
+        public_values: None,
+        vkey_hash: None,
+        error: Some(error.to_string()),
+    }
+}
 
-    // Aggregate metrics
-    let mut trader = [0u8; 20];
-    trader.copy_from_slice(&bytes[0..20]);
-
-    let trade_count = u32::from_be_bytes(bytes[20..24].try_into().unwrap());
-    let win_count = u32::from_be_bytes(bytes[24..28].try_into().unwrap());
-    let total_pnl_micros = i64::from_be_bytes(bytes[28..36].try_into().unwrap());
-    let total_collateral_micros = u64::from_be_bytes(bytes[36..44].try_into().unwrap());
-    let start_timestamp = u64::from_be_bytes(bytes[44..52].try_into().unwrap());
-    let end_timestamp = u64::from_be_bytes(bytes[52..60].try_into().unwrap());
-
-    // Featured position
-    let featured_trade_id = u64::from_be_bytes(bytes[60..68].try_into().unwrap());
-    let featured_pair_index = u32::from_be_bytes(bytes[68..72].try_into().unwrap());
-    let featured_is_buy = bytes[72] == 1;
-    let featured_leverage_raw = u32::from_be_bytes(bytes[73..77].try_into().unwrap());
-    let featured_collateral_micros = u64::from_be_bytes(bytes[77..85].try_into().unwrap());
-    let featured_entry_price_raw = u128::from_be_bytes(bytes[85..101].try_into().unwrap());
-    let featured_is_open = bytes[101] == 1;
-    let featured_timestamp = u64::from_be_bytes(bytes[102..110].try_into().unwrap());
-
-    let metrics = MetricsOutput {
-        trader: format!("0x{}", hex::encode(trader)),
-        trade_count,
-        win_count,
-        total_pnl: total_pnl_micros as f64 / 1_000_000.0,
-        total_collateral: total_collateral_micros as f64 / 1_000_000.0,
-        start_timestamp,
-        end_timestamp,
+/// Decode the versioned public values committed by the guest, via the shared
+/// `encoding` crate, and convert them into the host's display-friendly output
+/// types. Returns a [`DecodeError`] (rather than panicking) on an unknown
+/// schema version or a too-short slice, so callers can report it cleanly.
+fn decode_public_values(bytes: &[u8]) -> Result<(MetricsOutput, FeaturedOutput), DecodeError> {
+    let (metrics, featured) = encoding::decode(bytes)?;
+
+    let mean_return_micros = if metrics.trade_count > 0 {
+        metrics.total_pnl_micros as f64 / metrics.trade_count as f64
+    } else {
+        0.0
+    };
+    let return_stddev_micros = if metrics.trade_count > 0 {
+        let mean_sq = metrics.sum_sq_returns_micros as f64 / metrics.trade_count as f64;
+        (mean_sq - mean_return_micros * mean_return_micros).max(0.0).sqrt()
+    } else {
+        0.0
+    };
+
+    let metrics_out = MetricsOutput {
+        trader: format!("0x{}", hex::encode(metrics.trader)),
+        trade_count: metrics.trade_count,
+        win_count: metrics.win_count,
+        total_pnl: metrics.total_pnl_micros as f64 / 1_000_000.0,
+        total_collateral: metrics.total_collateral_micros as f64 / 1_000_000.0,
+        start_timestamp: metrics.start_timestamp,
+        end_timestamp: metrics.end_timestamp,
+        gross_pnl: metrics.gross_pnl_micros as f64 / 1_000_000.0,
+        total_cost: metrics.total_cost_micros as f64 / 1_000_000.0,
+        max_drawdown: metrics.max_drawdown_micros as f64 / 1_000_000.0,
+        mean_return: mean_return_micros / 1_000_000.0,
+        return_stddev: return_stddev_micros / 1_000_000.0,
     };
 
-    let featured = FeaturedOutput {
-        trade_id: featured_trade_id,
-        pair_index: featured_pair_index,
-        is_buy: featured_is_buy,
-        leverage: featured_leverage_raw as f64 / 100.0,
-        collateral: featured_collateral_micros as f64 / 1_000_000.0,
-        entry_price: featured_entry_price_raw as f64 / 1e18,
-        is_open: featured_is_open,
-        timestamp: featured_timestamp,
+    let featured_out = FeaturedOutput {
+        trade_id: featured.trade_id,
+        pair_index: featured.pair_index.get(),
+        side: featured.side,
+        leverage: featured.leverage as f64 / 100.0,
+        collateral: featured.collateral_micros as f64 / 1_000_000.0,
+        entry_price: featured.entry_price as f64 / 1e18,
+        is_open: featured.is_open == 1,
+        timestamp: featured.timestamp,
     };
 
-    (metrics, featured)
+    Ok((metrics_out, featured_out))
 }